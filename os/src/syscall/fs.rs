@@ -1,7 +1,8 @@
 //! File and filesystem-related syscalls
-use crate::fs::{open_file, OpenFlags, Stat, ROOT_INODE, search_file, StatMode, add_link, unlink};
+use crate::fs::{open_file, make_pipe, OpenFlags, Stat, ROOT_INODE, search_file, StatMode, add_link, unlink};
 use crate::mm::{translated_byte_buffer, translated_str, UserBuffer, translated_refmut};
 use crate::task::{current_task, current_user_token};
+use alloc::sync::Arc;
 
 pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
     trace!("kernel:pid[{}] sys_write", current_task().unwrap().pid.0);
@@ -75,6 +76,42 @@ pub fn sys_close(fd: usize) -> isize {
     0
 }
 
+/// chunk0-5: duplicate the file descriptor `fd` into a freshly allocated slot.
+pub fn sys_dup(fd: usize) -> isize {
+    trace!("kernel:pid[{}] sys_dup", current_task().unwrap().pid.0);
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if fd >= inner.fd_table.len() {
+        return -1;
+    }
+    if inner.fd_table[fd].is_none() {
+        return -1;
+    }
+    let new_fd = inner.alloc_fd();
+    inner.fd_table[new_fd] = Some(Arc::clone(inner.fd_table[fd].as_ref().unwrap()));
+    new_fd as isize
+}
+
+/// chunk0-5: create a pipe, writing the read/write fds into the user array.
+///
+/// `pipe_fds[0]` receives the read end and `pipe_fds[1]` the write end. The read
+/// end blocks (yields) while empty until data arrives or every write end is
+/// closed (EOF); the write end errors once every read end is gone.
+pub fn sys_pipe(pipe_fds: *mut usize) -> isize {
+    trace!("kernel:pid[{}] sys_pipe", current_task().unwrap().pid.0);
+    let task = current_task().unwrap();
+    let token = current_user_token();
+    let mut inner = task.inner_exclusive_access();
+    let (pipe_read, pipe_write) = make_pipe();
+    let read_fd = inner.alloc_fd();
+    inner.fd_table[read_fd] = Some(pipe_read);
+    let write_fd = inner.alloc_fd();
+    inner.fd_table[write_fd] = Some(pipe_write);
+    *translated_refmut(token, pipe_fds) = read_fd;
+    *translated_refmut(token, unsafe { pipe_fds.add(1) }) = write_fd;
+    0
+}
+
 /// YOUR JOB: Implement fstat.
 pub fn sys_fstat(_fd: usize, _st: *mut Stat) -> isize {
     trace!(
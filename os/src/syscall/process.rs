@@ -6,10 +6,12 @@ use alloc::sync::Arc;
 use crate::{
     config::{MAX_SYSCALL_NUM, BIG_STRIDE},
     fs::{open_file, OpenFlags},
-    mm::{translated_refmut, translated_str, VirtAddr, MapPermission},
+    mm::{translated_ref, translated_refmut, translated_str, VirtAddr, MapPermission},
     task::{
         add_task, current_task, current_user_token, exit_current_and_run_next,
-        suspend_current_and_run_next, TaskStatus, TaskControlBlock, pass_task_status, SyscallInfo, pass_syscall_info, push_current_area, release_current_area
+        suspend_current_and_run_next, block_current_and_run_next, TaskStatus, TaskControlBlock, pass_task_status, SyscallInfo, pass_syscall_info, push_current_area, release_current_area,
+        pid2task, SigAction, MAX_SIG, SIGKILL, SIGSEGV, SeccompFilter, SeccompAction, SIGCHLD, CLONE_VM,
+        list_all_tasks, ProcessStatus, TaskControlBlockInner,
     },
     timer::get_time_us,
 };
@@ -38,6 +40,12 @@ pub struct TaskInfo {
 /// task exits and submit an exit code
 pub fn sys_exit(exit_code: i32) -> ! {
     trace!("kernel:pid[{}] sys_exit", current_task().unwrap().pid.0);
+    // chunk1-2: a vfork child that exits without exec'ing must still wake its
+    // suspended parent (the common vfork + _exit path), mirroring sys_exec.
+    if let Some(parent) = current_task().unwrap().take_vfork_parent() {
+        parent.inner_exclusive_access().task_status = TaskStatus::Ready;
+        add_task(parent);
+    }
     exit_current_and_run_next(exit_code);
     panic!("Unreachable in sys_exit!");
 }
@@ -52,21 +60,56 @@ pub fn sys_getpid() -> isize {
     trace!("kernel: sys_getpid pid:{}", current_task().unwrap().pid.0);
     current_task().unwrap().pid.0 as isize
 }
-/// fork a process.
-pub fn sys_fork() -> isize {
-    trace!("kernel:pid[{}] sys_fork", current_task().unwrap().pid.0);
+/// chunk1-1: Linux-style clone. `fork` is `sys_clone(SIGCHLD, 0, 0, 0, 0)`.
+pub fn sys_clone(
+    flags: u32,
+    user_stack: usize,
+    _parent_tid: usize,
+    _child_tid: usize,
+    tls: usize,
+) -> isize {
+    trace!("kernel:pid[{}] sys_clone", current_task().unwrap().pid.0);
     let current_task = current_task().unwrap();
-    let new_task = current_task.fork();
+    let new_task = current_task.clone_task(flags, user_stack, tls);
     let new_pid = new_task.pid.0;
     // modify trap context of new_task, because it returns immediately after switching
     let trap_cx = new_task.inner_exclusive_access().get_trap_cx();
     // we do not have to move to next instruction since we have done it before
-    // for child process, fork returns 0
+    // for the child, clone returns 0
     trap_cx.x[10] = 0;
     // add new task to scheduler
     add_task(new_task);
     new_pid as isize
 }
+
+/// fork a process: a full-copy clone that signals the parent on exit.
+pub fn sys_fork() -> isize {
+    trace!("kernel:pid[{}] sys_fork", current_task().unwrap().pid.0);
+    sys_clone(SIGCHLD, 0, 0, 0, 0)
+}
+/// chunk1-2: vfork a process. The child shares the parent's address space and
+/// the parent is suspended (not re-queued) until the child execs or exits.
+pub fn sys_vfork() -> isize {
+    trace!("kernel:pid[{}] sys_vfork", current_task().unwrap().pid.0);
+    let parent = current_task().unwrap();
+    // CLONE_VM: share the address space; no TLS or stack override.
+    let child = parent.clone_task(CLONE_VM, 0, 0);
+    let new_pid = child.pid.0;
+    // child returns 0
+    let trap_cx = child.inner_exclusive_access().get_trap_cx();
+    trap_cx.x[10] = 0;
+    // back-reference so the child can wake us on exec/exit
+    child.inner_exclusive_access().vfork_parent = Some(Arc::downgrade(&parent));
+    add_task(child);
+    drop(parent);
+    // suspend the parent: mark it Blocked and actually deschedule it, so it is
+    // removed from the run path entirely (not merely flagged) and cannot race the
+    // child in the shared address space. sys_exec/exit re-queues it later.
+    block_current_and_run_next();
+    // resumed here once the child execs or exits
+    new_pid as isize
+}
+
 /// exec a new process.
 pub fn sys_exec(path: *const u8) -> isize {
     trace!("kernel:pid[{}] sys_exec", current_task().unwrap().pid.0);
@@ -76,15 +119,31 @@ pub fn sys_exec(path: *const u8) -> isize {
         let all_data = app_inode.read_all();
         let task = current_task().unwrap();
         task.exec(all_data.as_slice());
+        // chunk1-2: a vfork child has stopped borrowing the shared address
+        // space, so resume the suspended parent.
+        if let Some(parent) = task.take_vfork_parent() {
+            parent.inner_exclusive_access().task_status = TaskStatus::Ready;
+            add_task(parent);
+        }
         0
     } else {
         -1
     }
 }
 
-/// If there is not a child process whose pid is same as given, return -1.
-/// Else if there is a child process but it is still running, return -2.
-pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
+/// chunk1-6: do not block; return 0 instead of -2 when no child is ready.
+const WNOHANG: usize = 1;
+/// chunk1-6: also report stopped/traced children without reaping them.
+const WUNTRACED: usize = 2;
+
+/// Wait for a child to change state.
+///
+/// Returns -1 if there is no matching child. With `WNOHANG` set, returns 0
+/// (instead of -2) when a matching child is still running. The status written
+/// back is encoded Linux-style: an exited child reports `(exit_code & 0xff) << 8`
+/// so `WIFEXITED`/`WEXITSTATUS` decode correctly, while a stopped child (under
+/// `WUNTRACED`) reports `(sig << 8) | 0x7f` so `WIFSTOPPED` holds.
+pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32, options: usize) -> isize {
     trace!("kernel: sys_waitpid");
     let task = current_task().unwrap();
     // find a child process
@@ -99,6 +158,25 @@ pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
         return -1;
         // ---- release current PCB
     }
+    // chunk1-6: WUNTRACED first reports an actually stopped/traced child without
+    // reaping it. Gate strictly on the ptrace stop state — a vfork parent's
+    // Blocked child is not "stopped" in the waitpid sense.
+    if options & WUNTRACED != 0 {
+        let stopped = inner.children.iter().find_map(|p| {
+            let p_inner = p.inner_exclusive_access();
+            if (pid == -1 || pid as usize == p.getpid()) && p_inner.ptrace.stopped {
+                Some((p.getpid(), p_inner.ptrace.stop_sig))
+            } else {
+                None
+            }
+        });
+        if let Some((found_pid, stop_sig)) = stopped {
+            // WIFSTOPPED encoding: real stop signal in the high byte, 0x7f low
+            *translated_refmut(inner.memory_set.exclusive_access().token(), exit_code_ptr) =
+                ((stop_sig as i32) << 8) | 0x7f;
+            return found_pid as isize;
+        }
+    }
     let pair = inner.children.iter().enumerate().find(|(_, p)| {
         // ++++ temporarily access child PCB exclusively
         p.inner_exclusive_access().is_zombie() && (pid == -1 || pid as usize == p.getpid())
@@ -112,8 +190,12 @@ pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
         // ++++ temporarily access child PCB exclusively
         let exit_code = child.inner_exclusive_access().exit_code;
         // ++++ release child PCB
-        *translated_refmut(inner.memory_set.token(), exit_code_ptr) = exit_code;
+        // exit code occupies the high byte so WEXITSTATUS recovers it
+        *translated_refmut(inner.memory_set.exclusive_access().token(), exit_code_ptr) = (exit_code & 0xff) << 8;
         found_pid as isize
+    } else if options & WNOHANG != 0 {
+        // chunk1-6: non-blocking — nothing ready yet
+        0
     } else {
         -2
     }
@@ -177,12 +259,42 @@ pub fn sys_task_info(_ti: *mut TaskInfo) -> isize {
         *time_pa = (get_time_us() / 1000) - sys_info.time;
         for i in 0..MAX_SYSCALL_NUM {
             let syscall_pa = translated_refmut(token, (syscall_va_base + 4 * i) as *mut u32);
-            *syscall_pa = sys_info.syscall_times[i];
+            *syscall_pa = sys_info.syscall_times[i].0;
         }
     }
     0
 }
 
+/// chunk0-4: one entry per syscall number returned by `sys_syscall_profile`.
+#[repr(C)]
+pub struct SyscallProfileEntry {
+    /// number of times this syscall has been issued
+    pub count: u32,
+    /// accumulated cycles spent inside its handler
+    pub time: usize,
+}
+
+/// chunk0-4: copy the per-syscall profiling table into a user buffer.
+///
+/// Writes `MAX_SYSCALL_NUM` [`SyscallProfileEntry`] records starting at `buf`,
+/// so userspace can see which syscalls dominate this task's runtime. `#[repr(C)]`
+/// gives the struct a defined layout, unlike a plain tuple, so `size_of` is a
+/// reliable stride (mirrors `sys_list_procs`/`ProcRecord`).
+pub fn sys_syscall_profile(buf: *mut u8) -> isize {
+    trace!("kernel:pid[{}] sys_syscall_profile", current_task().unwrap().pid.0);
+    let token = current_user_token();
+    let sys_info: SyscallInfo = pass_syscall_info();
+    let base = buf as *mut SyscallProfileEntry;
+    let stride = core::mem::size_of::<SyscallProfileEntry>();
+    for i in 0..MAX_SYSCALL_NUM {
+        let (count, time) = sys_info.syscall_times[i];
+        let entry_pa = translated_refmut(token, (base as usize + i * stride) as *mut SyscallProfileEntry);
+        entry_pa.count = count;
+        entry_pa.time = time;
+    }
+    0
+}
+
 /// YOUR JOB: Implement mmap.
 pub fn sys_mmap(_start: usize, _len: usize, _port: usize) -> isize {
     trace!(
@@ -276,6 +388,370 @@ pub fn sys_spawn(_path: *const u8) -> isize {
 
 }
 
+/// chunk0-3: create a new thread sharing the current process's address space.
+///
+/// Allocates a process-local tid plus its user stack and trap-context page,
+/// builds a runnable thread whose `memory_set` aliases the parent's and whose
+/// `fd_table` is a separate copy of the parent's (same open files; later opens
+/// and closes are not shared), and hands it to the scheduler. Returns the new
+/// tid.
+pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
+    trace!("kernel:pid[{}] sys_thread_create", current_task().unwrap().pid.0);
+    let task = current_task().unwrap();
+    let new_task = task.thread_create(entry, arg);
+    let tid = new_task
+        .inner_exclusive_access()
+        .res
+        .as_ref()
+        .unwrap()
+        .tid;
+    add_task(new_task);
+    tid as isize
+}
+
+/// chunk0-3: return the tid of the current thread.
+pub fn sys_gettid() -> isize {
+    trace!("kernel:pid[{}] sys_gettid", current_task().unwrap().pid.0);
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .res
+        .as_ref()
+        .unwrap()
+        .tid as isize
+}
+
+/// chunk0-3: wait for a sibling thread `tid` to finish and reap its resources.
+///
+/// Returns -1 if no such thread exists, -2 if it is still running, otherwise the
+/// thread's exit code after freeing its tid-indexed user stack and trap context.
+pub fn sys_waittid(tid: usize) -> isize {
+    trace!("kernel:pid[{}] sys_waittid", current_task().unwrap().pid.0);
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    // a thread may not wait for itself
+    if inner.res.as_ref().unwrap().tid == tid {
+        return -1;
+    }
+    drop(inner);
+    task.waittid(tid)
+}
+
+/// chunk1-4: ptrace requests.
+const PTRACE_TRACEME: usize = 0;
+const PTRACE_PEEKDATA: usize = 2;
+const PTRACE_POKEDATA: usize = 5;
+const PTRACE_CONT: usize = 7;
+const PTRACE_ATTACH: usize = 16;
+const PTRACE_DETACH: usize = 17;
+const PTRACE_SINGLESTEP: usize = 9;
+const PTRACE_GETREGS: usize = 12;
+const PTRACE_SETREGS: usize = 13;
+
+/// chunk1-4: whether `tracer_pid` is the registered tracer of `tracee_inner`.
+///
+/// Every request but TRACEME/ATTACH must pass this before touching a tracee's
+/// memory, registers, or run state — otherwise any task could inspect or
+/// resume any other task just by knowing its pid.
+fn is_tracer(tracee_inner: &TaskControlBlockInner, tracer_pid: usize) -> bool {
+    tracee_inner.ptrace.tracer == Some(tracer_pid)
+}
+
+/// chunk1-4: debugger interface. `request` selects the operation; `pid` names
+/// the tracee (ignored for TRACEME), and `addr`/`data` are request-specific.
+pub fn sys_ptrace(request: usize, pid: usize, addr: usize, data: usize) -> isize {
+    trace!("kernel:pid[{}] sys_ptrace", current_task().unwrap().pid.0);
+    match request {
+        PTRACE_TRACEME => {
+            let task = current_task().unwrap();
+            let mut inner = task.inner_exclusive_access();
+            inner.ptrace.traced = true;
+            inner.ptrace.tracer = inner.parent.as_ref().and_then(|p| p.upgrade()).map(|p| p.pid.0);
+            0
+        }
+        PTRACE_ATTACH => {
+            let tracer = current_task().unwrap().pid.0;
+            if let Some(tracee) = pid2task(pid) {
+                let mut inner = tracee.inner_exclusive_access();
+                inner.ptrace.traced = true;
+                inner.ptrace.tracer = Some(tracer);
+                // the tracee is NOT stopped here — it keeps running and parks
+                // itself at its next syscall/trap via `TaskControlBlockInner::
+                // trap_stop`, which is where a stop can actually deschedule it.
+                0
+            } else {
+                -1
+            }
+        }
+        PTRACE_DETACH => {
+            if let Some(tracee) = pid2task(pid) {
+                let mut inner = tracee.inner_exclusive_access();
+                if !is_tracer(&inner, current_task().unwrap().pid.0) {
+                    return -1;
+                }
+                inner.ptrace = Default::default();
+                0
+            } else {
+                -1
+            }
+        }
+        PTRACE_PEEKDATA => {
+            if let Some(tracee) = pid2task(pid) {
+                if !is_tracer(&tracee.inner_exclusive_access(), current_task().unwrap().pid.0) {
+                    return -1;
+                }
+                let token = tracee.get_user_token();
+                *translated_ref(token, addr as *const usize) as isize
+            } else {
+                -1
+            }
+        }
+        PTRACE_POKEDATA => {
+            if let Some(tracee) = pid2task(pid) {
+                if !is_tracer(&tracee.inner_exclusive_access(), current_task().unwrap().pid.0) {
+                    return -1;
+                }
+                let token = tracee.get_user_token();
+                *translated_refmut(token, addr as *mut usize) = data;
+                0
+            } else {
+                -1
+            }
+        }
+        PTRACE_GETREGS => {
+            // copy the tracee's 32 general registers into the tracer's buffer at `data`
+            if let Some(tracee) = pid2task(pid) {
+                if !is_tracer(&tracee.inner_exclusive_access(), current_task().unwrap().pid.0) {
+                    return -1;
+                }
+                let token = current_user_token();
+                let regs = tracee.inner_exclusive_access().get_trap_cx().x;
+                for (i, reg) in regs.iter().enumerate() {
+                    *translated_refmut(token, (data + i * 8) as *mut usize) = *reg;
+                }
+                0
+            } else {
+                -1
+            }
+        }
+        PTRACE_SETREGS => {
+            // load the tracee's 32 general registers from the tracer's buffer at `data`
+            if let Some(tracee) = pid2task(pid) {
+                if !is_tracer(&tracee.inner_exclusive_access(), current_task().unwrap().pid.0) {
+                    return -1;
+                }
+                let token = current_user_token();
+                let trap_cx = tracee.inner_exclusive_access().get_trap_cx();
+                for i in 0..32 {
+                    trap_cx.x[i] = *translated_ref(token, (data + i * 8) as *const usize);
+                }
+                0
+            } else {
+                -1
+            }
+        }
+        PTRACE_CONT | PTRACE_SINGLESTEP => {
+            if let Some(tracee) = pid2task(pid) {
+                let mut inner = tracee.inner_exclusive_access();
+                // only the registered tracer may resume its own tracee
+                if !is_tracer(&inner, current_task().unwrap().pid.0) {
+                    return -1;
+                }
+                // only a genuinely stopped tracee may be resumed; continuing a
+                // running (or never-stopped) task would double-enqueue it
+                if !inner.ptrace.traced || !inner.ptrace.stopped {
+                    return -1;
+                }
+                inner.ptrace.stopped = false;
+                inner.ptrace.stop_sig = 0;
+                inner.ptrace.single_step = request == PTRACE_SINGLESTEP;
+                // restore the runnable status before re-queueing so the tracee's
+                // status is consistent with its queue membership
+                inner.task_status = TaskStatus::Ready;
+                drop(inner);
+                // the tracee was parked while stopped; put it back on the queue
+                add_task(tracee);
+                0
+            } else {
+                -1
+            }
+        }
+        _ => -1,
+    }
+}
+
+/// chunk1-5: errno returned to a task that issues a seccomp-denied syscall.
+const EPERM: isize = 1;
+/// chunk1-5: exit code for a task killed by its seccomp filter.
+const SECCOMP_KILL_EXIT_CODE: i32 = -(31 << 8);
+
+/// chunk1-5: consult the current task's seccomp filter before dispatching
+/// `syscall_id`.
+///
+/// Returns `None` when the call is permitted (the dispatcher proceeds),
+/// `Some(-EPERM)` when it must be denied with an error, and never returns under
+/// a KILL policy — the task is torn down via the normal exit path.
+pub fn seccomp_guard(syscall_id: usize) -> Option<isize> {
+    let task = current_task().unwrap();
+    let action = task.inner_exclusive_access().seccomp_action(syscall_id);
+    match action {
+        SeccompAction::Allow => None,
+        SeccompAction::ReturnErrno => Some(-EPERM),
+        SeccompAction::KillProcess => {
+            exit_current_and_run_next(SECCOMP_KILL_EXIT_CODE);
+            // unreachable: the task has been descheduled
+            Some(-EPERM)
+        }
+    }
+}
+
+/// chunk0-6: install (or tighten) a seccomp-style syscall filter on this task.
+///
+/// `mode` selects the default action for a disallowed syscall (0 = ReturnErrno,
+/// 1 = KillProcess); `allowed_bitmap_ptr` points at `MAX_SYSCALL_NUM` bits worth
+/// of allow words in user memory. An existing filter is only ever intersected
+/// with the new one, so a task can never loosen its own policy.
+pub fn sys_seccomp(mode: usize, allowed_bitmap_ptr: *const u64) -> isize {
+    trace!("kernel:pid[{}] sys_seccomp", current_task().unwrap().pid.0);
+    let action = match mode {
+        0 => SeccompAction::ReturnErrno,
+        1 => SeccompAction::KillProcess,
+        _ => return -1,
+    };
+    let token = current_user_token();
+    let mut allowed = [0u64; (MAX_SYSCALL_NUM + 63) / 64];
+    for (i, word) in allowed.iter_mut().enumerate() {
+        *word = *translated_ref(token, unsafe { allowed_bitmap_ptr.add(i) });
+    }
+    let new_filter = SeccompFilter::new(action, allowed);
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    match inner.seccomp.as_mut() {
+        // one-way tightening: intersect with the already installed filter
+        Some(existing) => existing.tighten(&new_filter),
+        None => inner.seccomp = Some(new_filter),
+    }
+    0
+}
+
+/// chunk0-1: post signal `sig` to the task whose pid is `pid`.
+pub fn sys_kill(pid: usize, sig: usize) -> isize {
+    trace!("kernel:pid[{}] sys_kill", current_task().unwrap().pid.0);
+    // 0 is not a real signal number (POSIX uses it only to probe pid existence)
+    if sig == 0 || sig >= MAX_SIG {
+        return -1;
+    }
+    if let Some(task) = pid2task(pid) {
+        task.inner_exclusive_access().add_signal(sig);
+        0
+    } else {
+        -1
+    }
+}
+
+/// chunk0-1: install a handler for `sig`, returning the previous one in `old`.
+pub fn sys_sigaction(sig: usize, new: *const SigAction, old: *mut SigAction) -> isize {
+    trace!("kernel:pid[{}] sys_sigaction", current_task().unwrap().pid.0);
+    // SIGKILL/SIGSEGV enact fixed default actions and must never be caught by a
+    // user handler.
+    if sig == 0 || sig >= MAX_SIG || sig == SIGKILL || sig == SIGSEGV || new.is_null() {
+        return -1;
+    }
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if !old.is_null() {
+        *translated_refmut(token, old) = inner.signal_actions[sig];
+    }
+    inner.signal_actions[sig] = *translated_ref(token, new);
+    0
+}
+
+/// chunk0-1: replace the blocked-signal mask, returning the previous mask.
+pub fn sys_sigprocmask(new_mask: u64) -> isize {
+    trace!("kernel:pid[{}] sys_sigprocmask", current_task().unwrap().pid.0);
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let old_mask = inner.signal_mask;
+    // SIGKILL/SIGSEGV can never be blocked, or their mandatory default actions
+    // (see `handle_pending_signals`) could be masked off indefinitely
+    inner.signal_mask = new_mask & !((1 << SIGKILL) | (1 << SIGSEGV));
+    old_mask as isize
+}
+
+/// chunk0-1: return from a user signal handler, restoring the saved trap context.
+pub fn sys_sigreturn() -> isize {
+    trace!("kernel:pid[{}] sys_sigreturn", current_task().unwrap().pid.0);
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if let Some(backup) = inner.trap_ctx_backup.take() {
+        inner.handling_sig = -1;
+        // restore the mask that was in effect before the handler ran
+        inner.signal_mask = inner.signal_mask_backup;
+        let trap_cx = inner.get_trap_cx();
+        *trap_cx = backup;
+        // return the restored a0, so writing the return value back into a0
+        // leaves the restored context unchanged
+        trap_cx.x[10] as isize
+    } else {
+        -1
+    }
+}
+
+/// chunk1-7: one record per live task returned by `sys_list_procs`.
+#[repr(C)]
+pub struct ProcRecord {
+    /// process id
+    pub pid: usize,
+    /// parent process id (0 for the root task)
+    pub ppid: usize,
+    /// rich process status
+    pub status: ProcessStatus,
+    /// scheduling priority
+    pub priority: usize,
+    /// accumulated scheduled-in time, in cycles
+    pub run_time: usize,
+    /// total number of syscalls issued
+    pub syscall_count: usize,
+}
+
+/// chunk1-7: enumerate the task table into a user buffer.
+///
+/// Fills up to `len` [`ProcRecord`]s at `buf`, one per live task, and returns
+/// the number of records written. Each field is written through
+/// `translated_refmut` so a record straddling a page boundary is handled.
+pub fn sys_list_procs(buf: *mut ProcRecord, len: usize) -> isize {
+    trace!("kernel:pid[{}] sys_list_procs", current_task().unwrap().pid.0);
+    let token = current_user_token();
+    let tasks = list_all_tasks();
+    let count = tasks.len().min(len);
+    let stride = core::mem::size_of::<ProcRecord>();
+    for (i, task) in tasks.iter().take(count).enumerate() {
+        let inner = task.inner_exclusive_access();
+        let ppid = inner
+            .parent
+            .as_ref()
+            .and_then(|p| p.upgrade())
+            .map(|p| p.pid.0)
+            .unwrap_or(0);
+        let syscall_count: usize = inner
+            .taskinfo
+            .syscall_times
+            .iter()
+            .map(|(c, _)| *c as usize)
+            .sum();
+        let base = buf as usize + i * stride;
+        // write each field individually to cope with page-split records
+        *translated_refmut(token, base as *mut usize) = task.pid.0;
+        *translated_refmut(token, (base + 8) as *mut usize) = ppid;
+        *translated_refmut(token, (base + 16) as *mut ProcessStatus) = inner.process_status();
+        *translated_refmut(token, (base + 24) as *mut usize) = inner.taskinfo.priority;
+        *translated_refmut(token, (base + 32) as *mut usize) = inner.taskinfo.cpu_time;
+        *translated_refmut(token, (base + 40) as *mut usize) = syscall_count;
+    }
+    count as isize
+}
+
 /// YOUR JOB: Set task priority.
 /// Set task priority
 pub fn sys_set_priority(_prio: isize) -> isize {
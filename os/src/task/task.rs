@@ -1,7 +1,7 @@
 //! Types related to task management & Functions for completely changing TCB
 use super::TaskContext;
 use super::{kstack_alloc, pid_alloc, KernelStack, PidHandle};
-use crate::config::{TRAP_CONTEXT_BASE, MAX_SYSCALL_NUM, BIG_STRIDE};
+use crate::config::{TRAP_CONTEXT_BASE, MAX_SYSCALL_NUM, BIG_STRIDE, PAGE_SIZE, USER_STACK_SIZE, SIGNAL_TRAMPOLINE};
 use crate::fs::{File, Stdin, Stdout};
 use crate::mm::{MemorySet, PhysPageNum, VirtAddr, KERNEL_SPACE, MapPermission, judge_allocation, judge_free};
 use crate::sync::UPSafeCell;
@@ -35,7 +35,7 @@ impl TaskControlBlock {
     /// Get the address of app's page table
     pub fn get_user_token(&self) -> usize {
         let inner = self.inner_exclusive_access();
-        inner.memory_set.token()
+        inner.memory_set.exclusive_access().token()
     }
 
     /// Lab2:
@@ -48,9 +48,9 @@ impl TaskControlBlock {
             return -1;
         }
 
-        let mut inner = self.inner_exclusive_access();
+        let inner = self.inner_exclusive_access();
         // not allocated before, so we simply use insert_framed_area here to finish our mappings.
-        inner.memory_set.insert_framed_area(start_va, end_va, permission);
+        inner.memory_set.exclusive_access().insert_framed_area(start_va, end_va, permission);
         0
     }
 
@@ -64,18 +64,32 @@ impl TaskControlBlock {
             return -1;
         }
 
-        let mut inner = self.inner_exclusive_access();
+        let inner = self.inner_exclusive_access();
         // not freed before, so we simply use set_munmap to release this part.
-        if inner.memory_set.set_munmap(start_va, end_va) == false {
+        if inner.memory_set.exclusive_access().set_munmap(start_va, end_va) == false {
             return -1;
         }
         0
     }
-    /// Lab3: 
+    /// Lab3:
     /// return the stride of the task.
     pub fn get_stride(&self) -> Stride {
         self.inner_exclusive_access().taskinfo.stride
     }
+
+    /// chunk1-3: seed this task's stride to `stride`.
+    ///
+    /// The scheduler uses this when enqueueing a freshly forked/spawned child,
+    /// setting it to the current minimum live stride so the newcomer is neither
+    /// starved nor unfairly prioritised.
+    pub fn set_stride(&self, stride: Stride) {
+        self.inner_exclusive_access().taskinfo.stride = stride;
+    }
+
+    /// chunk1-3: advance this task's stride by its pass after being scheduled.
+    pub fn add_stride(&self) {
+        self.inner_exclusive_access().taskinfo.add_stride();
+    }
 }
 
 
@@ -94,8 +108,13 @@ pub struct TaskControlBlockInner {
     /// Maintain the execution status of the current process
     pub task_status: TaskStatus,
 
-    /// Application address space
-    pub memory_set: MemorySet,
+    /// Application address space.
+    ///
+    /// chunk0-3: shared via `Arc<UPSafeCell<_>>` (the same pattern as
+    /// `tid_allocator`) so every thread of a process sees the same area
+    /// bookkeeping — a thread's own `insert_framed_area`/`remove_area_with_
+    /// start_vpn` calls are then visible to siblings that didn't make them.
+    pub memory_set: Arc<UPSafeCell<MemorySet>>,
 
     /// Parent process of the current process.
     /// Weak will not affect the reference count of the parent
@@ -118,6 +137,43 @@ pub struct TaskControlBlockInner {
     /// Syscall info
     pub taskinfo: SyscallInfo,
 
+    /// chunk0-1: pending signals, one bit per signal number
+    pub signals: u64,
+
+    /// chunk0-1: currently blocked signals
+    pub signal_mask: u64,
+
+    /// chunk0-1: the signal number being handled, -1 if none
+    pub handling_sig: isize,
+
+    /// chunk0-1: blocked mask saved at delivery, restored on sigreturn
+    pub signal_mask_backup: u64,
+
+    /// chunk0-1: registered handler table, indexed by signal number
+    pub signal_actions: [SigAction; MAX_SIG + 1],
+
+    /// chunk0-1: saved trap context while a user handler runs, restored on sigreturn
+    pub trap_ctx_backup: Option<TrapContext>,
+
+    /// chunk0-3: per-thread resources (tid, user stack, trap-context area).
+    /// `None` for the bare process created before threads are split out.
+    pub res: Option<TaskUserRes>,
+
+    /// chunk0-3: process-wide recycling allocator for thread ids, shared by all
+    /// threads of a process so tids never collide across sibling threads.
+    pub tid_allocator: Arc<UPSafeCell<RecycleAllocator>>,
+
+    /// chunk0-6: installed seccomp-style syscall filter, inherited across
+    /// `fork`/`spawn`. `None` means the task is unfiltered.
+    pub seccomp: Option<SeccompFilter>,
+
+    /// chunk1-2: for a vfork child, the parent suspended until this task execs
+    /// or exits. `None` for ordinary tasks.
+    pub vfork_parent: Option<Weak<TaskControlBlock>>,
+
+    /// chunk1-4: ptrace state for this task (tracer link, stop flags).
+    pub ptrace: PtraceState,
+
     // Lab4: The BMap tree
     // Get the stat of the fd.
     //pub map: BTreeMap<usize, Stat>,
@@ -131,7 +187,7 @@ impl TaskControlBlockInner {
     }
     /// get the user token
     pub fn get_user_token(&self) -> usize {
-        self.memory_set.token()
+        self.memory_set.exclusive_access().token()
     }
     pub fn get_status(&self) -> TaskStatus {
         self.task_status
@@ -152,8 +208,12 @@ impl TaskControlBlockInner {
         self.taskinfo
     }
 
-    pub fn add_one_syscall(&mut self, sys_num: usize) {
-        self.taskinfo.syscall_times[sys_num] += 1;
+    /// chunk0-4: record one invocation of `sys_num`, accumulating both the call
+    /// count and the `elapsed` cycles spent inside the handler.
+    pub fn add_one_syscall(&mut self, sys_num: usize, elapsed: usize) {
+        let (count, time) = &mut self.taskinfo.syscall_times[sys_num];
+        *count += 1;
+        *time += elapsed;
     }
 
 }
@@ -174,6 +234,8 @@ impl TaskControlBlock {
         let kernel_stack = kstack_alloc();
         let kernel_stack_top = kernel_stack.get_top();
         // push a task context which goes to trap_return to the top of kernel stack
+        let tid_allocator = Arc::new(unsafe { UPSafeCell::new(RecycleAllocator::new()) });
+        let res = alloc_main_thread_res(&tid_allocator, trap_cx_ppn);
         let task_control_block = Self {
             pid: pid_handle,
             kernel_stack,
@@ -183,7 +245,7 @@ impl TaskControlBlock {
                     base_size: user_sp,
                     task_cx: TaskContext::goto_trap_return(kernel_stack_top),
                     task_status: TaskStatus::Ready,
-                    memory_set,
+                    memory_set: Arc::new(unsafe { UPSafeCell::new(memory_set) }),
                     parent: None,
                     children: Vec::new(),
                     exit_code: 0,
@@ -198,12 +260,25 @@ impl TaskControlBlock {
                     heap_bottom: user_sp,
                     program_brk: user_sp,
                     taskinfo: SyscallInfo {
-                        syscall_times: [0; MAX_SYSCALL_NUM],
+                        syscall_times: [(0, 0); MAX_SYSCALL_NUM],
                         time: 0,
+                        start_time: 0,
+                        cpu_time: 0,
                         stride: Stride(0),
                         pass: BIG_STRIDE / 16,
                         priority: 16,
                     },
+                    signals: 0,
+                    signal_mask: 0,
+                    handling_sig: -1,
+                    signal_mask_backup: 0,
+                    signal_actions: [SigAction::default(); MAX_SIG + 1],
+                    trap_ctx_backup: None,
+                    res: Some(res),
+                    tid_allocator,
+                    seccomp: None,
+                    vfork_parent: None,
+                    ptrace: PtraceState::default(),
                     //map: BTreeMap::<usize, Stat>::new(),
                     //namemap: BTreeMap::<String, usize>::new(),
                 })
@@ -232,8 +307,14 @@ impl TaskControlBlock {
 
         // **** access current TCB exclusively
         let mut inner = self.inner_exclusive_access();
-        // substitute memory_set
-        inner.memory_set = memory_set;
+        // substitute memory_set: exec replaces the whole address space, so this
+        // task gets a fresh, un-shared Arc rather than mutating the old one in
+        // place. NOTE: this does not reap any thread siblings spawned via
+        // thread_create/CLONE_VM off of this task (that would mean forcibly
+        // exiting another already-running task, which has no call site in this
+        // tree outside its own exit path) — they keep running against their old,
+        // now-orphaned memory_set Arc until they exit or are waittid'd.
+        inner.memory_set = Arc::new(unsafe { UPSafeCell::new(memory_set) });
         // update trap_cx ppn
         inner.trap_cx_ppn = trap_cx_ppn;
         // initialize trap_cx
@@ -250,10 +331,17 @@ impl TaskControlBlock {
 
     /// parent process fork the child process
     pub fn fork(self: &Arc<TaskControlBlock>) -> Arc<TaskControlBlock> {
+        // chunk1-3: compute before taking parent_inner below — current_min_stride()
+        // walks every live task including this one, so holding our own borrow
+        // here would double-borrow this same UPSafeCell and panic.
+        let min_stride = current_min_stride();
         // ---- hold parent PCB lock
         let mut parent_inner = self.inner_exclusive_access();
-        // copy user space(include trap context)
-        let memory_set = MemorySet::from_existed_user(&parent_inner.memory_set);
+        // chunk0-2: copy-on-write clone of the parent's address space. Framed,
+        // writable mappings are shared read-only in both page tables (with the
+        // COW bit set) and the underlying frames gain a reference; the actual
+        // frame copy is deferred to the StorePageFault handler in the trap layer.
+        let memory_set = MemorySet::from_existed_user_cow(&mut parent_inner.memory_set.exclusive_access());
         let trap_cx_ppn = memory_set
             .translate(VirtAddr::from(TRAP_CONTEXT_BASE).into())
             .unwrap()
@@ -284,6 +372,8 @@ impl TaskControlBlock {
             new_namemap.insert(*name, *fd);
         }
         */
+        let tid_allocator = Arc::new(unsafe { UPSafeCell::new(RecycleAllocator::new()) });
+        let res = alloc_main_thread_res(&tid_allocator, trap_cx_ppn);
         let task_control_block = Arc::new(TaskControlBlock {
             pid: pid_handle,
             kernel_stack,
@@ -293,7 +383,9 @@ impl TaskControlBlock {
                     base_size: parent_inner.base_size,
                     task_cx: TaskContext::goto_trap_return(kernel_stack_top),
                     task_status: TaskStatus::Ready,
-                    memory_set,
+                    // a fork is a new process: it gets its own address space,
+                    // not a handle shared with the parent
+                    memory_set: Arc::new(unsafe { UPSafeCell::new(memory_set) }),
                     parent: Some(Arc::downgrade(self)),
                     children: Vec::new(),
                     exit_code: 0,
@@ -301,12 +393,25 @@ impl TaskControlBlock {
                     heap_bottom: parent_inner.heap_bottom,
                     program_brk: parent_inner.program_brk,
                     taskinfo: SyscallInfo {
-                        syscall_times: [0; MAX_SYSCALL_NUM],
+                        syscall_times: [(0, 0); MAX_SYSCALL_NUM],
                         time: 0,
+                        start_time: 0,
+                        cpu_time: 0,
                         stride: Stride(0),
                         pass: BIG_STRIDE / 16,
                         priority: 16,
                     },
+                    signals: 0,
+                    signal_mask: 0,
+                    handling_sig: -1,
+                    signal_mask_backup: 0,
+                    signal_actions: [SigAction::default(); MAX_SIG + 1],
+                    trap_ctx_backup: None,
+                    res: Some(res),
+                    tid_allocator,
+                    seccomp: None,
+                    vfork_parent: None,
+                    ptrace: PtraceState::default(),
                     //map: new_map,
                     //namemap: new_namemap,
                 })
@@ -314,6 +419,10 @@ impl TaskControlBlock {
         });
         // add child
         parent_inner.children.push(task_control_block.clone());
+        // chunk0-6: an installed seccomp filter is inherited by the child
+        task_control_block.inner_exclusive_access().seccomp = parent_inner.seccomp;
+        // chunk1-3: seed the child to the current minimum live stride
+        task_control_block.set_stride(min_stride);
         // modify kernel_sp in trap_cx
         // **** access child PCB exclusively
         let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
@@ -324,8 +433,156 @@ impl TaskControlBlock {
         // ---- release parent PCB
     }
 
+    /// chunk1-1: Linux-style clone backing `sys_clone`.
+    ///
+    /// Honors `CLONE_VM` (share the parent's address space through the same
+    /// `memory_set` Arc; without it the child gets a copy-on-write copy),
+    /// `CLONE_SETTLS` (write `tls` into the child's
+    /// thread-pointer register), and a non-zero `user_stack` (override the
+    /// child's sp). With `flags == SIGCHLD` this is exactly the old `fork`.
+    pub fn clone_task(
+        self: &Arc<TaskControlBlock>,
+        flags: u32,
+        user_stack: usize,
+        tls: usize,
+    ) -> Arc<TaskControlBlock> {
+        // chunk1-3: compute before taking parent_inner below — current_min_stride()
+        // walks every live task including this one, so holding our own borrow
+        // here would double-borrow this same UPSafeCell and panic.
+        let min_stride = current_min_stride();
+        let mut parent_inner = self.inner_exclusive_access();
+        // a CLONE_VM (thread-like) child shares the process-wide tid allocator;
+        // a full clone is a new process and gets its own.
+        let tid_allocator = if flags & CLONE_VM != 0 {
+            parent_inner.tid_allocator.clone()
+        } else {
+            Arc::new(unsafe { UPSafeCell::new(RecycleAllocator::new()) })
+        };
+        // CLONE_VM shares the address space; otherwise we take a fresh COW copy.
+        // A CLONE_VM child still needs its OWN trap-context page so that patching
+        // its registers does not clobber the parent's live trap context.
+        let (memory_set, trap_cx_ppn, thread_res) = if flags & CLONE_VM != 0 {
+            let tid = tid_allocator.exclusive_access().alloc();
+            let mut res = TaskUserRes {
+                tid,
+                ustack_base: THREAD_USTACK_BASE,
+                trap_cx_ppn: PhysPageNum(0),
+            };
+            // a CLONE_VM child is thread-like: it needs its own user stack mapped
+            // into the shared space, the same way thread_create does, or waittid
+            // will later try to unmap a stack area that was never inserted
+            let ustack_bottom = res.ustack_bottom();
+            let ustack_top = res.ustack_top();
+            let trap_cx_va = res.trap_cx_base();
+            let mut ms = parent_inner.memory_set.exclusive_access();
+            ms.insert_framed_area(
+                VirtAddr::from(ustack_bottom),
+                VirtAddr::from(ustack_top),
+                MapPermission::R | MapPermission::W | MapPermission::U,
+            );
+            ms.insert_framed_area(
+                VirtAddr::from(trap_cx_va),
+                VirtAddr::from(trap_cx_va + PAGE_SIZE),
+                MapPermission::R | MapPermission::W,
+            );
+            let ppn = ms.translate(VirtAddr::from(trap_cx_va).into()).unwrap().ppn();
+            drop(ms);
+            res.trap_cx_ppn = ppn;
+            // inherit the parent's register state into the child's own page
+            *ppn.get_mut() = *parent_inner.get_trap_cx();
+            // CLONE_VM really does share: the child gets the same Arc, so its
+            // insert_framed_area calls above are visible to every sibling, not
+            // just a point-in-time copy of the parent's page table
+            let memory_set = Arc::clone(&parent_inner.memory_set);
+            (memory_set, ppn, Some(res))
+        } else {
+            let memory_set =
+                MemorySet::from_existed_user_cow(&mut parent_inner.memory_set.exclusive_access());
+            let ppn = memory_set
+                .translate(VirtAddr::from(TRAP_CONTEXT_BASE).into())
+                .unwrap()
+                .ppn();
+            // a full clone is a new process: its own tid-0 "main thread" res,
+            // same as fork/spawn, so sys_gettid/sys_waittid don't unwrap None
+            let res = alloc_main_thread_res(&tid_allocator, ppn);
+            (Arc::new(unsafe { UPSafeCell::new(memory_set) }), ppn, Some(res))
+        };
+        let pid_handle = pid_alloc();
+        let kernel_stack = kstack_alloc();
+        let kernel_stack_top = kernel_stack.get_top();
+        // copy fd table
+        let mut new_fd_table: Vec<Option<Arc<dyn File + Send + Sync>>> = Vec::new();
+        for fd in parent_inner.fd_table.iter() {
+            if let Some(file) = fd {
+                new_fd_table.push(Some(file.clone()));
+            } else {
+                new_fd_table.push(None);
+            }
+        }
+        let task_control_block = Arc::new(TaskControlBlock {
+            pid: pid_handle,
+            kernel_stack,
+            inner: unsafe {
+                UPSafeCell::new(TaskControlBlockInner {
+                    trap_cx_ppn,
+                    base_size: parent_inner.base_size,
+                    task_cx: TaskContext::goto_trap_return(kernel_stack_top),
+                    task_status: TaskStatus::Ready,
+                    memory_set,
+                    parent: Some(Arc::downgrade(self)),
+                    children: Vec::new(),
+                    exit_code: 0,
+                    fd_table: new_fd_table,
+                    heap_bottom: parent_inner.heap_bottom,
+                    program_brk: parent_inner.program_brk,
+                    taskinfo: SyscallInfo {
+                        syscall_times: [(0, 0); MAX_SYSCALL_NUM],
+                        time: 0,
+                        start_time: 0,
+                        cpu_time: 0,
+                        stride: Stride(0),
+                        pass: BIG_STRIDE / 16,
+                        priority: 16,
+                    },
+                    signals: 0,
+                    signal_mask: 0,
+                    handling_sig: -1,
+                    signal_mask_backup: 0,
+                    signal_actions: [SigAction::default(); MAX_SIG + 1],
+                    trap_ctx_backup: None,
+                    res: thread_res,
+                    tid_allocator,
+                    seccomp: None,
+                    vfork_parent: None,
+                    ptrace: PtraceState::default(),
+                })
+            },
+        });
+        parent_inner.children.push(task_control_block.clone());
+        // chunk0-6: inherit the parent's seccomp filter
+        task_control_block.inner_exclusive_access().seccomp = parent_inner.seccomp;
+        // chunk1-3: seed the cloned child to the current minimum live stride
+        task_control_block.set_stride(min_stride);
+        // patch the child's trap context
+        let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
+        trap_cx.kernel_sp = kernel_stack_top;
+        if user_stack != 0 {
+            // x[2] is sp
+            trap_cx.x[2] = user_stack;
+        }
+        if flags & CLONE_SETTLS != 0 {
+            // x[4] is tp, the thread pointer
+            trap_cx.x[4] = tls;
+        }
+        task_control_block
+    }
+
     /// lab3: reproduce
     pub fn spawn(self: &Arc<TaskControlBlock>, elf_data: &[u8]) -> Arc<TaskControlBlock> {
+        // chunk1-3: compute before taking father_inner below — current_min_stride()
+        // walks every live task including this one, so holding our own borrow
+        // here would double-borrow this same UPSafeCell and panic.
+        let min_stride = current_min_stride();
         // memory_set with elf program headers/trampoline/trap context/user stack
         let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
         let trap_cx_ppn = memory_set
@@ -337,7 +594,9 @@ impl TaskControlBlock {
         let kernel_stack = kstack_alloc();
         let kernel_stack_top = kernel_stack.get_top();
         // push a task context which goes to trap_return to the top of kernel stack
-        
+        let tid_allocator = Arc::new(unsafe { UPSafeCell::new(RecycleAllocator::new()) });
+        let res = alloc_main_thread_res(&tid_allocator, trap_cx_ppn);
+
         let mut father_inner = self.inner_exclusive_access();
         // copy fd table
         let mut new_fd_table: Vec<Option<Arc<dyn File + Send + Sync>>> = Vec::new();
@@ -370,7 +629,7 @@ impl TaskControlBlock {
                     base_size: user_sp,
                     task_cx: TaskContext::goto_trap_return(kernel_stack_top),
                     task_status: TaskStatus::Ready,
-                    memory_set,
+                    memory_set: Arc::new(unsafe { UPSafeCell::new(memory_set) }),
                     parent: Some(Arc::downgrade(self)),
                     children: Vec::new(),
                     exit_code: 0,
@@ -378,18 +637,35 @@ impl TaskControlBlock {
                     heap_bottom: user_sp,
                     program_brk: user_sp,
                     taskinfo: SyscallInfo {
-                        syscall_times: [0; MAX_SYSCALL_NUM],
+                        syscall_times: [(0, 0); MAX_SYSCALL_NUM],
                         time: 0,
+                        start_time: 0,
+                        cpu_time: 0,
                         stride: Stride(0),
                         pass: BIG_STRIDE / 16,
                         priority: 16,
                     },
+                    signals: 0,
+                    signal_mask: 0,
+                    handling_sig: -1,
+                    signal_mask_backup: 0,
+                    signal_actions: [SigAction::default(); MAX_SIG + 1],
+                    trap_ctx_backup: None,
+                    res: Some(res),
+                    tid_allocator,
+                    seccomp: None,
+                    vfork_parent: None,
+                    ptrace: PtraceState::default(),
                     // map: new_map,
                     // namemap: new_namemap,
                 })
             },
         });
         father_inner.children.push(task_control_block.clone());
+        // chunk0-6: an installed seccomp filter is inherited by the spawned child
+        task_control_block.inner_exclusive_access().seccomp = father_inner.seccomp;
+        // chunk1-3: seed the spawned child to the current minimum live stride
+        task_control_block.set_stride(min_stride);
         drop(father_inner);
         // prepare TrapContext in user space
         let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
@@ -403,6 +679,170 @@ impl TaskControlBlock {
         task_control_block
     }
 
+    /// chunk0-3: create a new thread that shares this task's address space.
+    ///
+    /// A tid is taken from the process-local allocator; the thread's user stack
+    /// and trap-context page are mapped into the shared `memory_set` at the tid's
+    /// deterministic slots, and a runnable child TCB is returned with a copy of
+    /// the parent's `fd_table` holding the same open files. The two tables are
+    /// separate: an fd opened or closed later by one thread is not seen by the
+    /// other.
+    pub fn thread_create(self: &Arc<TaskControlBlock>, entry: usize, arg: usize) -> Arc<TaskControlBlock> {
+        // chunk1-3: compute before taking parent_inner below — current_min_stride()
+        // walks every live task including this one, so holding our own borrow
+        // here would double-borrow this same UPSafeCell and panic.
+        let min_stride = current_min_stride();
+        let mut parent_inner = self.inner_exclusive_access();
+        let tid = parent_inner.tid_allocator.exclusive_access().alloc();
+        let tid_allocator = parent_inner.tid_allocator.clone();
+        let ustack_base = THREAD_USTACK_BASE;
+        // map the new thread's user stack and trap-context page into the shared space
+        let mut res = TaskUserRes {
+            tid,
+            ustack_base,
+            // filled in once the trap-context page has been mapped below
+            trap_cx_ppn: PhysPageNum(0),
+        };
+        let ustack_bottom = res.ustack_bottom();
+        let ustack_top = res.ustack_top();
+        let trap_cx_va = res.trap_cx_base();
+        {
+            let mut ms = parent_inner.memory_set.exclusive_access();
+            ms.insert_framed_area(
+                VirtAddr::from(ustack_bottom),
+                VirtAddr::from(ustack_top),
+                MapPermission::R | MapPermission::W | MapPermission::U,
+            );
+            ms.insert_framed_area(
+                VirtAddr::from(trap_cx_va),
+                VirtAddr::from(trap_cx_va + PAGE_SIZE),
+                MapPermission::R | MapPermission::W,
+            );
+            res.trap_cx_ppn = ms
+                .translate(VirtAddr::from(trap_cx_va).into())
+                .unwrap()
+                .ppn();
+        }
+        let trap_cx_ppn = res.trap_cx_ppn;
+
+        // copy fd table so the new thread shares open files
+        let mut new_fd_table: Vec<Option<Arc<dyn File + Send + Sync>>> = Vec::new();
+        for fd in parent_inner.fd_table.iter() {
+            if let Some(file) = fd {
+                new_fd_table.push(Some(file.clone()));
+            } else {
+                new_fd_table.push(None);
+            }
+        }
+        // a thread shares the parent's page table AND area bookkeeping: the
+        // same Arc, not a fresh MemorySet that merely points at shared frames.
+        // Otherwise a sibling thread that didn't create this thread's stack/
+        // trap-cx area would have no record of it in its own memory_set, and
+        // `waittid` called from that sibling couldn't find the area to free.
+        let memory_set = Arc::clone(&parent_inner.memory_set);
+
+        let pid_handle = pid_alloc();
+        let kernel_stack = kstack_alloc();
+        let kernel_stack_top = kernel_stack.get_top();
+        let task_control_block = Arc::new(TaskControlBlock {
+            pid: pid_handle,
+            kernel_stack,
+            inner: unsafe {
+                UPSafeCell::new(TaskControlBlockInner {
+                    trap_cx_ppn,
+                    base_size: parent_inner.base_size,
+                    task_cx: TaskContext::goto_trap_return(kernel_stack_top),
+                    task_status: TaskStatus::Ready,
+                    memory_set,
+                    parent: Some(Arc::downgrade(self)),
+                    children: Vec::new(),
+                    exit_code: 0,
+                    fd_table: new_fd_table,
+                    heap_bottom: parent_inner.heap_bottom,
+                    program_brk: parent_inner.program_brk,
+                    taskinfo: SyscallInfo {
+                        syscall_times: [(0, 0); MAX_SYSCALL_NUM],
+                        time: 0,
+                        start_time: 0,
+                        cpu_time: 0,
+                        stride: Stride(0),
+                        pass: BIG_STRIDE / 16,
+                        priority: 16,
+                    },
+                    signals: 0,
+                    signal_mask: 0,
+                    handling_sig: -1,
+                    signal_mask_backup: 0,
+                    signal_actions: [SigAction::default(); MAX_SIG + 1],
+                    trap_ctx_backup: None,
+                    res: Some(res),
+                    tid_allocator,
+                    seccomp: None,
+                    vfork_parent: None,
+                    ptrace: PtraceState::default(),
+                })
+            },
+        });
+        parent_inner.children.push(task_control_block.clone());
+        // chunk0-6: the new thread inherits the process's seccomp filter
+        task_control_block.inner_exclusive_access().seccomp = parent_inner.seccomp;
+        // chunk1-3: seed the new thread to the current minimum live stride
+        task_control_block.set_stride(min_stride);
+        drop(parent_inner);
+        // the new thread starts at `entry` with `arg` in a0 on its own stack
+        let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
+        *trap_cx = TrapContext::app_init_context(
+            entry,
+            ustack_top,
+            KERNEL_SPACE.exclusive_access().token(),
+            kernel_stack_top,
+            trap_handler as usize,
+        );
+        trap_cx.x[10] = arg;
+        task_control_block
+    }
+
+    /// chunk0-3: reap a finished sibling thread `tid`, freeing its tid-indexed
+    /// user stack and trap-context area. Returns -1 / -2 / exit code.
+    pub fn waittid(self: &Arc<TaskControlBlock>, tid: usize) -> isize {
+        let mut inner = self.inner_exclusive_access();
+        let pair = inner.children.iter().enumerate().find(|(_, child)| {
+            let child_inner = child.inner_exclusive_access();
+            child_inner
+                .res
+                .as_ref()
+                .map(|r| r.tid == tid)
+                .unwrap_or(false)
+        });
+        let Some((idx, _)) = pair else {
+            return -1;
+        };
+        let child = inner.children[idx].clone();
+        let child_inner = child.inner_exclusive_access();
+        if !child_inner.is_zombie() {
+            return -2;
+        }
+        let exit_code = child_inner.exit_code;
+        // free the tid-indexed resources from the shared address space
+        if let Some(res) = child_inner.res.as_ref() {
+            let mut ms = inner.memory_set.exclusive_access();
+            ms.remove_area_with_start_vpn(VirtAddr::from(res.ustack_bottom()).into());
+            ms.remove_area_with_start_vpn(VirtAddr::from(res.trap_cx_base()).into());
+            drop(ms);
+            inner.tid_allocator.exclusive_access().dealloc(res.tid);
+        }
+        drop(child_inner);
+        inner.children.remove(idx);
+        exit_code as isize
+    }
+
+    /// chunk1-2: if this task was vforked, detach and return the suspended
+    /// parent so the caller can mark it `Ready` and re-queue it. Idempotent.
+    pub fn take_vfork_parent(&self) -> Option<Arc<TaskControlBlock>> {
+        let mut inner = self.inner_exclusive_access();
+        inner.vfork_parent.take().and_then(|p| p.upgrade())
+    }
+
     /// get pid of process
     pub fn getpid(&self) -> usize {
         self.pid.0
@@ -417,15 +857,13 @@ impl TaskControlBlock {
         if new_brk < heap_bottom as isize {
             return None;
         }
+        let mut ms = inner.memory_set.exclusive_access();
         let result = if size < 0 {
-            inner
-                .memory_set
-                .shrink_to(VirtAddr(heap_bottom), VirtAddr(new_brk as usize))
+            ms.shrink_to(VirtAddr(heap_bottom), VirtAddr(new_brk as usize))
         } else {
-            inner
-                .memory_set
-                .append_to(VirtAddr(heap_bottom), VirtAddr(new_brk as usize))
+            ms.append_to(VirtAddr(heap_bottom), VirtAddr(new_brk as usize))
         };
+        drop(ms);
         if result {
             inner.program_brk = new_brk as usize;
             Some(old_break)
@@ -444,36 +882,129 @@ pub enum TaskStatus {
     Ready,
     /// running
     Running,
+    /// chunk1-2: suspended (e.g. a vfork parent waiting for its child), not
+    /// placed back on the ready queue until explicitly woken
+    Blocked,
     /// exited
     Zombie,
 }
 
-/// Lab3:
-/// implement Stride type here. 
+/// chunk1-7: richer process state reported by `sys_list_procs`.
+///
+/// Extends [`TaskStatus`] with the zombie/stopped/traced distinctions the task
+/// layer already tracks across its ptrace and vfork machinery.
+#[derive(Copy, Clone, PartialEq)]
+#[repr(usize)]
+pub enum ProcessStatus {
+    /// runnable or currently running
+    Run = 0,
+    /// blocked / suspended (e.g. a vfork parent)
+    Sleep = 1,
+    /// stopped for a tracer
+    Stop = 2,
+    /// stopped and traced
+    Tracing = 3,
+    /// exited, awaiting reap
+    Zombie = 4,
+    /// fully reaped
+    Dead = 5,
+}
+
+impl TaskControlBlockInner {
+    /// chunk1-7: map this task's internal state to a [`ProcessStatus`].
+    pub fn process_status(&self) -> ProcessStatus {
+        if self.is_zombie() {
+            ProcessStatus::Zombie
+        } else if self.ptrace.stopped {
+            if self.ptrace.traced {
+                ProcessStatus::Tracing
+            } else {
+                ProcessStatus::Stop
+            }
+        } else if self.get_status() == TaskStatus::Blocked {
+            ProcessStatus::Sleep
+        } else {
+            ProcessStatus::Run
+        }
+    }
+}
+
+/// Lab3 / chunk1-3:
+/// A stride counter with overflow-safe comparison.
+///
+/// Strides are stored as a fixed-width `u64` and compared by treating
+/// `a.wrapping_sub(b)` as a signed value: if its high bit is set, `a < b`.
+/// Because every priority is forced `>= 2`, each `pass <= BIG_STRIDE / 2`, so
+/// the spread between the largest and smallest live stride never exceeds
+/// `BIG_STRIDE / 2` and the signed-difference test is unambiguous across
+/// wraparound.
 #[derive(Copy, Clone)]
-pub struct Stride(usize);
+pub struct Stride(pub u64);
 
 impl Stride {
-    // initialize:
-    pub fn new(_t: usize) -> Self {
-        Stride(_t)
+    /// initialize a stride counter.
+    pub fn new(t: u64) -> Self {
+        Stride(t)
+    }
+    /// the raw stride value.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+    /// accumulate `pass`, wrapping on overflow.
+    pub fn step(&mut self, pass: u64) {
+        self.0 = self.0.wrapping_add(pass);
+    }
+}
+
+/// chunk1-3: the lowest stride among every currently known task.
+///
+/// Seeds a freshly created child here instead of at its parent's stride, so a
+/// newcomer lands at the live minimum and is neither starved behind a
+/// far-advanced parent nor, on the flip side, favoured over tasks that are
+/// further along. Falls back to `Stride(0)` when there are no other tasks yet
+/// (e.g. creating initproc).
+fn current_min_stride() -> Stride {
+    crate::task::list_all_tasks()
+        .iter()
+        .map(|t| t.get_stride())
+        .fold(None, |min: Option<Stride>, s| match min {
+            Some(m) if m < s => Some(m),
+            _ => Some(s),
+        })
+        .unwrap_or(Stride::new(0))
+}
+
+/// chunk0-3: tid-0 `TaskUserRes` for a task's own first/only thread.
+///
+/// Every task needs a `res`, not just ones spun up via `thread_create`/
+/// `CLONE_VM`: `sys_gettid`/`sys_waittid` unconditionally unwrap it. Allocates
+/// tid 0 from `tid_allocator` (guaranteed fresh here, so this is always the
+/// first id handed out) and reuses the trap-context page the caller already
+/// mapped for the process itself.
+fn alloc_main_thread_res(
+    tid_allocator: &Arc<UPSafeCell<RecycleAllocator>>,
+    trap_cx_ppn: PhysPageNum,
+) -> TaskUserRes {
+    let tid = tid_allocator.exclusive_access().alloc();
+    TaskUserRes {
+        tid,
+        ustack_base: THREAD_USTACK_BASE,
+        trap_cx_ppn,
     }
 }
 
 impl PartialOrd for Stride {
-    // We tend to return the min value.
+    // Order by the signed interpretation of the wrapping difference so that the
+    // smallest-stride task is selected correctly even after wraparound.
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        if self.0 - other.0 > BIG_STRIDE / 2 {
-            return Some(self.0.cmp(&other.0));
-        }
-        Some(other.0.cmp(&self.0))
+        let diff = self.0.wrapping_sub(other.0) as i64;
+        Some(diff.cmp(&0))
     }
 }
 
-
 impl PartialEq for Stride {
-    fn eq(&self, _other: &Self) -> bool {
-        false
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
     }
 }
 
@@ -482,10 +1013,14 @@ impl PartialEq for Stride {
 /// The syscall info of a task.
 #[derive(Copy, Clone)]
 pub struct SyscallInfo {
-    /// The numbers of syscall called by task
-    pub syscall_times: [u32; MAX_SYSCALL_NUM],
+    /// chunk0-4: per-syscall `(call count, accumulated cycles)` tuples.
+    pub syscall_times: [(u32, usize); MAX_SYSCALL_NUM],
     /// Total running time of a task.
     pub time: usize,
+    /// chunk0-4: timestamp of the most recent schedule-in, in cycles.
+    pub start_time: usize,
+    /// chunk0-4: total cycles this task has been scheduled in.
+    pub cpu_time: usize,
     /// Stride so far.
     pub stride: Stride,
     /// Every Pass for a stride.
@@ -495,8 +1030,331 @@ pub struct SyscallInfo {
 }
 
 impl SyscallInfo {
-    /// add pass for the stride.
+    /// add pass for the stride, wrapping on overflow.
     pub fn add_stride(&mut self) {
-        self.stride.0 += self.pass;
+        self.stride.step(self.pass as u64);
+    }
+}
+
+/// chunk1-4: per-task ptrace state.
+///
+/// Tracks whether the task is being traced, which task is its tracer, whether
+/// it is currently stopped for the tracer, and whether the next resume should
+/// single-step.
+#[derive(Copy, Clone)]
+pub struct PtraceState {
+    /// whether this task is traced (PTRACE_TRACEME / PTRACE_ATTACH)
+    pub traced: bool,
+    /// pid of the tracer, if attached
+    pub tracer: Option<usize>,
+    /// whether the tracee is currently stopped awaiting a tracer command
+    pub stopped: bool,
+    /// whether to single-step on the next PTRACE_CONT/SINGLESTEP
+    pub single_step: bool,
+    /// signal number that caused the current stop (0 when not stopped)
+    pub stop_sig: usize,
+}
+
+impl Default for PtraceState {
+    fn default() -> Self {
+        Self {
+            traced: false,
+            tracer: None,
+            stopped: false,
+            single_step: false,
+            stop_sig: 0,
+        }
+    }
+}
+
+/// chunk0-6: action taken by a seccomp filter for a disallowed syscall.
+#[derive(Copy, Clone, PartialEq)]
+pub enum SeccompAction {
+    /// let the syscall through
+    Allow,
+    /// return `-EPERM` to the caller
+    ReturnErrno,
+    /// terminate the task with a distinguished exit code
+    KillProcess,
+}
+
+/// chunk0-6: per-task syscall filter.
+///
+/// `allowed` is a compact bitmap over syscall numbers; a set bit permits that
+/// syscall, a clear bit triggers `action`. Borrowed from the Starnix seccomp
+/// layer. Filters can only ever be tightened (see [`SeccompFilter::tighten`]).
+#[derive(Copy, Clone)]
+pub struct SeccompFilter {
+    /// action applied to a syscall whose bit is not set in `allowed`
+    pub action: SeccompAction,
+    /// one bit per syscall number
+    pub allowed: [u64; (MAX_SYSCALL_NUM + 63) / 64],
+}
+
+impl SeccompFilter {
+    /// build a filter from an allow-bitmap and default action
+    pub fn new(action: SeccompAction, allowed: [u64; (MAX_SYSCALL_NUM + 63) / 64]) -> Self {
+        Self { action, allowed }
+    }
+    /// whether `sys_num` is permitted by this filter
+    pub fn is_allowed(&self, sys_num: usize) -> bool {
+        if sys_num >= MAX_SYSCALL_NUM {
+            return false;
+        }
+        self.allowed[sys_num / 64] & (1 << (sys_num % 64)) != 0
+    }
+    /// chunk0-6: tighten `self` with `other`, never loosening.
+    ///
+    /// The resulting allow-set is the intersection of the two, so an installed
+    /// filter can only ever forbid more syscalls, never fewer.
+    pub fn tighten(&mut self, other: &SeccompFilter) {
+        for (word, bits) in self.allowed.iter_mut().zip(other.allowed.iter()) {
+            *word &= *bits;
+        }
+        // a kill action dominates a plain errno action
+        if other.action == SeccompAction::KillProcess {
+            self.action = SeccompAction::KillProcess;
+        }
+    }
+}
+
+/// chunk0-1: one past the highest signal number supported (SIGKILL/SIGSEGV fit
+/// well inside).
+pub const MAX_SIG: usize = 64;
+
+/// chunk0-1: default-action signals handled directly by the kernel.
+pub const SIGKILL: usize = 9;
+/// chunk0-1: segmentation violation, default action terminates the task.
+pub const SIGSEGV: usize = 11;
+/// chunk1-4: trap signal reported when a traced task stops at a syscall/trap.
+pub const SIGTRAP: usize = 5;
+
+/// chunk1-1: clone flag — share the parent's address space instead of copying.
+pub const CLONE_VM: u32 = 0x0000_0100;
+/// chunk1-1: clone flag — set the child's TLS (thread pointer) register.
+pub const CLONE_SETTLS: u32 = 0x0008_0000;
+/// chunk1-1: the signal a child delivers to its parent on exit; plain `fork`.
+pub const SIGCHLD: u32 = 17;
+
+/// chunk0-3: base above which every thread's user stack is laid out.
+///
+/// Deliberately NOT `heap_bottom`: `sys_sbrk`/`change_program_brk` grow the
+/// process heap upward from `heap_bottom`, so reusing it here would let a
+/// process that both `sbrk`s and creates a thread map the heap and a thread's
+/// stack into overlapping virtual ranges. This sits a fixed distance below the
+/// per-thread trap-context pages (which themselves count down from
+/// `TRAP_CONTEXT_BASE`), leaving room for far more trap-context pages than any
+/// realistic thread count would ever need.
+const THREAD_USTACK_BASE: usize = TRAP_CONTEXT_BASE - 0x0010_0000;
+
+/// chunk0-1: a registered action for one signal number.
+///
+/// Mirrors the Starnix `SigAction`: a user handler entry point, the additional
+/// mask applied while the handler runs, and a small flag word.
+#[derive(Copy, Clone)]
+pub struct SigAction {
+    /// user-space handler entry point, 0 means "default action"
+    pub handler: usize,
+    /// signals masked for the duration of the handler
+    pub mask: u64,
+    /// behaviour flags (unused for now, kept for ABI compatibility)
+    pub flags: u32,
+}
+
+impl Default for SigAction {
+    fn default() -> Self {
+        Self {
+            handler: 0,
+            mask: 0,
+            flags: 0,
+        }
+    }
+}
+
+/// chunk0-3: a recycling allocator that hands out small contiguous ids and
+/// reuses freed ones first. Used process-locally for thread ids.
+pub struct RecycleAllocator {
+    current: usize,
+    recycled: Vec<usize>,
+}
+
+impl RecycleAllocator {
+    /// create an empty allocator
+    pub fn new() -> Self {
+        Self {
+            current: 0,
+            recycled: Vec::new(),
+        }
+    }
+    /// hand out an id, reusing a recycled one when available
+    pub fn alloc(&mut self) -> usize {
+        if let Some(id) = self.recycled.pop() {
+            id
+        } else {
+            self.current += 1;
+            self.current - 1
+        }
+    }
+    /// return an id to the free list
+    pub fn dealloc(&mut self, id: usize) {
+        assert!(id < self.current);
+        assert!(
+            !self.recycled.iter().any(|i| *i == id),
+            "id {} has been deallocated!",
+            id
+        );
+        self.recycled.push(id);
+    }
+}
+
+impl Default for RecycleAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// chunk0-3: resources that belong to a single thread within a process.
+///
+/// Each thread gets its own user stack and trap-context page, placed
+/// deterministically from its tid so that siblings never overlap.
+pub struct TaskUserRes {
+    /// thread id, allocated from the process-local allocator
+    pub tid: usize,
+    /// base above which this process's thread stacks are laid out
+    pub ustack_base: usize,
+    /// physical frame backing this thread's trap context
+    pub trap_cx_ppn: PhysPageNum,
+}
+
+impl TaskUserRes {
+    /// top of this thread's user stack
+    pub fn ustack_top(&self) -> usize {
+        self.ustack_base + (self.tid + 1) * (USER_STACK_SIZE + PAGE_SIZE)
+    }
+    /// virtual address of this thread's user-stack bottom (guard page below)
+    pub fn ustack_bottom(&self) -> usize {
+        self.ustack_base + self.tid * (USER_STACK_SIZE + PAGE_SIZE) + PAGE_SIZE
+    }
+    /// virtual address of this thread's trap context
+    ///
+    /// Offset strictly below `TRAP_CONTEXT_BASE` (which the process's own trap
+    /// context occupies), so tid 0 does not alias the already-mapped process
+    /// trap page and collide on the first `thread_create`/CLONE_VM clone.
+    pub fn trap_cx_base(&self) -> usize {
+        TRAP_CONTEXT_BASE - (self.tid + 1) * PAGE_SIZE
+    }
+}
+
+impl TaskControlBlockInner {
+    /// chunk0-1: set the pending bit for `sig` on this task.
+    pub fn add_signal(&mut self, sig: usize) {
+        if sig < MAX_SIG {
+            self.signals |= 1 << sig;
+        }
+    }
+
+    /// chunk1-4: mark this traced task stopped on a syscall/trap.
+    ///
+    /// Invoked from `trap_handler` when a traced task hits a syscall or trap:
+    /// it records the stop so `sys_waitpid`'s WUNTRACED path can report it, and
+    /// returns whether the caller must now park the task (block-and-schedule)
+    /// until the tracer issues PTRACE_CONT/SINGLESTEP.
+    pub fn trap_stop(&mut self) -> bool {
+        if self.ptrace.traced && !self.ptrace.stopped {
+            self.ptrace.stopped = true;
+            self.ptrace.stop_sig = SIGTRAP;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// chunk0-4: stamp the schedule-in time, in cycles.
+    ///
+    /// Called by the scheduler when this task is switched in so that the span
+    /// until the next `sched_out` can be added to `cpu_time`.
+    pub fn sched_in(&mut self, now: usize) {
+        self.taskinfo.start_time = now;
+    }
+
+    /// chunk0-4: accumulate the time spent scheduled in since `sched_in`.
+    pub fn sched_out(&mut self, now: usize) {
+        self.taskinfo.cpu_time += now.saturating_sub(self.taskinfo.start_time);
+    }
+
+    /// chunk0-2: resolve a copy-on-write store fault at `vpn`.
+    ///
+    /// Invoked from the `StorePageFault` arm of `trap_handler` when a write hits
+    /// a page whose COW bit is set. Delegates to the address space, which
+    /// inspects the faulting frame's reference count (maintained by the frame
+    /// allocator): if more than one task shares it, a fresh frame is allocated,
+    /// the old contents copied, the faulting `vpn` remapped writable to the new
+    /// frame and the shared count decremented; if exactly one, write permission
+    /// is simply restored in place. Returns `true` when the fault was a genuine
+    /// COW page that is now writable, `false` for a real protection violation.
+    pub fn resolve_cow_fault(&mut self, vpn: crate::mm::VirtPageNum) -> bool {
+        self.memory_set.exclusive_access().cow_fault(vpn)
+    }
+
+    /// chunk1-5: resolve the seccomp action for `sys_num`.
+    ///
+    /// An unfiltered task, or one whose filter permits the syscall, yields
+    /// `Allow`; otherwise the filter's configured default action is returned.
+    pub fn seccomp_action(&self, sys_num: usize) -> SeccompAction {
+        match &self.seccomp {
+            Some(filter) if !filter.is_allowed(sys_num) => filter.action,
+            _ => SeccompAction::Allow,
+        }
+    }
+
+    /// chunk0-1: the lowest-numbered deliverable signal, if any.
+    ///
+    /// A signal is deliverable when it is pending and not currently blocked.
+    pub fn fetch_signal(&self) -> Option<usize> {
+        let deliverable = self.signals & !self.signal_mask;
+        (0..MAX_SIG).find(|&sig| deliverable & (1 << sig) != 0)
+    }
+
+    /// chunk0-1: deliver one pending signal on the trap return path.
+    ///
+    /// Called from `trap_handler` just before returning to user space. Picks the
+    /// lowest deliverable signal (`pending & !blocked`); for a registered handler
+    /// it saves the interrupted `TrapContext` and the current mask, rewrites
+    /// `sepc` to the handler and `a0` to the signal number, and temporarily adds
+    /// the handler's mask (plus the signal itself) to `blocked`. A signal with no
+    /// handler uses its default action: `Some(exit_code)` is returned for the
+    /// fatal defaults (SIGKILL/SIGSEGV) so the caller can terminate the task via
+    /// the existing exit path; otherwise `None`.
+    pub fn handle_pending_signals(&mut self) -> Option<i32> {
+        // never nest user handlers; the current one must sigreturn first
+        if self.handling_sig != -1 {
+            return None;
+        }
+        let sig = self.fetch_signal()?;
+        // consume the pending bit
+        self.signals &= !(1 << sig);
+        let action = self.signal_actions[sig];
+        if action.handler == 0 {
+            // default action: the fatal signals terminate the task
+            return match sig {
+                SIGKILL | SIGSEGV => Some(-(sig as i32)),
+                _ => None,
+            };
+        }
+        self.handling_sig = sig as isize;
+        self.signal_mask_backup = self.signal_mask;
+        // block the handler's mask and the signal itself for the handler's run
+        self.signal_mask |= action.mask | (1 << sig);
+        // save the interrupted context so sys_sigreturn can restore it exactly,
+        // then redirect execution into the user handler with the signal in a0
+        let trap_cx = self.get_trap_cx();
+        self.trap_ctx_backup = Some(*trap_cx);
+        trap_cx.sepc = action.handler;
+        trap_cx.x[10] = sig;
+        // set ra to the sigreturn trampoline so the handler's `ret` traps back
+        // into sys_sigreturn, which restores the saved context and clears
+        // handling_sig — without this a second signal could never be delivered.
+        trap_cx.x[1] = SIGNAL_TRAMPOLINE;
+        None
     }
 }